@@ -15,16 +15,17 @@ const COLORS: &'static [Color] = &[
 ];
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Node {
     pub layer: u8,
-    pub has_key: bool,
+    pub key: Option<u8>,
 }
 
 impl Default for Node {
     fn default() -> Node {
         Node {
             layer: 0,
-            has_key: false,
+            key: None,
         }
     }
 }
@@ -42,10 +43,9 @@ impl fmt::Debug for Node {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let string = self.layer.to_string().color(COLORS[self.layer as usize]);
 
-        if self.has_key {
-            f.write_fmt(format_args!("{}", string.reversed()))
-        } else {
-            f.write_fmt(format_args!("{}", string))
+        match self.key {
+            Some(_) => f.write_fmt(format_args!("{}", string.reversed())),
+            None => f.write_fmt(format_args!("{}", string)),
         }
     }
 }
@@ -1,6 +1,11 @@
 use colored::*;
-use rand::{seq::IteratorRandom, Rng};
-use std::collections::HashSet;
+use rand::{
+    rngs::StdRng,
+    seq::{IteratorRandom, SliceRandom},
+    Rng, SeedableRng,
+};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt;
 
 use crate::node::Node;
@@ -9,6 +14,13 @@ use crate::pos::{ConnectState, Connection, Line, Pos};
 const DIRECTIONS: &'static [Pos] = &[Pos(-1, 0), Pos(1, 0), Pos(0, -1), Pos(0, 1)];
 const RETRIES: i32 = 10;
 
+/// `solve`'s key mask is a `u32`, so a locked door's layer bit can't be
+/// represented past this many layers. `generate_once` actually produces a
+/// final node one layer beyond `opts.layers` (`generate_final_state`'s extra
+/// increment), so this is one lower than the mask's highest bit index (31)
+/// to leave room for that.
+const MAX_LAYERS: u8 = 30;
+
 const CONN_V: &'static str = "│";
 const CONN_H: &'static str = "─";
 const LOCK: &'static str = "╳";
@@ -61,13 +73,51 @@ impl BetterFormatter for fmt::Formatter<'_> {
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NodeMap {
     nodes: Vec<Option<Node>>,
-    connections: Vec<Connection>,
+    #[cfg_attr(feature = "serde", serde(with = "connections_as_vec"))]
+    connections: HashMap<Line, ConnectState>,
+    connection_order: Vec<Line>,
     width: isize,
     height: isize,
 }
 
+/// `HashMap<Line, ConnectState>` can't derive `Serialize`/`Deserialize`
+/// through serde_json and other self-describing formats, since `Line`
+/// isn't a string. Serialize it as the `Connection` list instead, in the
+/// map's (unspecified) iteration order; `connection_order` is serialized as
+/// its own field.
+#[cfg(feature = "serde")]
+mod connections_as_vec {
+    use super::{ConnectState, Connection, HashMap, Line};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S>(
+        connections: &HashMap<Line, ConnectState>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        connections
+            .iter()
+            .map(|(&line, &state)| Connection { line, state })
+            .collect::<Vec<Connection>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<HashMap<Line, ConnectState>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(Vec::<Connection>::deserialize(deserializer)?
+            .into_iter()
+            .map(|conn| (conn.line, conn.state))
+            .collect())
+    }
+}
+
 fn color(index: u8) -> Color {
     COLORS[index as usize]
 }
@@ -120,7 +170,7 @@ impl NodeMap {
         let line = Line(pos, pos + offset);
 
         let arg = match self.get_connection(line) {
-            Some(conn) => func(conn),
+            Some(conn) => func(&conn),
             None => T::from(" "),
         };
 
@@ -194,30 +244,187 @@ impl fmt::Debug for NodeMap {
     }
 }
 
+/// Selects how a generated node layout is wired together with connections.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionMode {
+    /// The original per-layer, adjacency-stitching strategy.
+    Adjacent,
+    /// Builds a spanning tree over every placed node with a union-find, so
+    /// the skeleton is guaranteed connected before shortcuts are layered on.
+    SpanningTree,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct GenerateOpts {
+    pub width: isize,
+    pub height: isize,
+    pub layers: u8,
+    pub mode: ConnectionMode,
+}
+
 impl NodeMap {
     pub fn with_size(width: usize, height: usize) -> NodeMap {
         let nodes = std::iter::repeat_with(|| None)
             .take(width * height)
             .collect();
 
-        let connections = vec![];
-
         NodeMap {
             nodes,
-            connections,
+            connections: HashMap::new(),
+            connection_order: vec![],
             width: width as isize,
             height: height as isize,
         }
     }
 
     pub fn generate(width: isize, height: isize, layers: u8) -> Result<NodeMap, String> {
-        let mut rng = rand::thread_rng();
+        Self::generate_with(GenerateOpts {
+            width,
+            height,
+            layers,
+            mode: ConnectionMode::Adjacent,
+        })
+    }
+
+    pub fn generate_with(opts: GenerateOpts) -> Result<NodeMap, String> {
+        Self::generate_with_rng(opts, &mut rand::thread_rng()).map(|(node_map, _)| node_map)
+    }
+
+    /// Generates a dungeon from a fixed `seed`: the same seed and parameters
+    /// always produce an identical map, which makes bug reports and tests
+    /// reproducible.
+    pub fn generate_seeded(
+        width: isize,
+        height: isize,
+        layers: u8,
+        seed: u64,
+    ) -> Result<NodeMap, String> {
+        Self::generate_with_seeded(
+            GenerateOpts {
+                width,
+                height,
+                layers,
+                mode: ConnectionMode::Adjacent,
+            },
+            seed,
+        )
+    }
+
+    /// Like `generate_with`, but seeded: the same seed and `opts` always
+    /// produce an identical map. Unlike `generate_seeded`, this composes with
+    /// every `ConnectionMode`.
+    pub fn generate_with_seeded(opts: GenerateOpts, seed: u64) -> Result<NodeMap, String> {
+        Self::generate_with_rng(opts, &mut StdRng::seed_from_u64(seed))
+            .map(|(node_map, _)| node_map)
+    }
+
+    /// Like `generate_with`, but also returns the solve path found while
+    /// validating solvability, so callers that need it (e.g. `score`) don't
+    /// have to run the BFS a second time.
+    fn generate_with_rng<R: Rng>(
+        opts: GenerateOpts,
+        rng: &mut R,
+    ) -> Result<(NodeMap, Vec<Pos>), String> {
+        if opts.layers > MAX_LAYERS {
+            return Err(format!(
+                "layers must be <= {} to fit the solver's key bitmask (got {})",
+                MAX_LAYERS, opts.layers
+            ));
+        }
+
+        for _ in 0..RETRIES {
+            let mut node_map =
+                NodeMap::generate_once(opts.width, opts.height, opts.layers, rng)?;
+
+            if opts.mode == ConnectionMode::SpanningTree {
+                node_map.connect_spanning_tree(rng);
+            }
+
+            if let Some(path) = node_map.solve() {
+                return Ok((node_map, path));
+            }
+        }
+
+        Err(format!(
+            "Failed to generate a solvable map after {} retries",
+            RETRIES
+        ))
+    }
+
+    /// Generates `candidates` independent dungeons in parallel and returns
+    /// the highest-scoring one that is solvable, per `score`.
+    pub fn generate_best(
+        width: isize,
+        height: isize,
+        layers: u8,
+        candidates: usize,
+    ) -> Result<NodeMap, String> {
+        let opts = GenerateOpts {
+            width,
+            height,
+            layers,
+            mode: ConnectionMode::Adjacent,
+        };
+
+        (0..candidates)
+            .into_par_iter()
+            .filter_map(|_| NodeMap::generate_with_rng(opts, &mut rand::thread_rng()).ok())
+            .map(|(node_map, path)| {
+                let score = node_map.score(&path);
+                (node_map, score)
+            })
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+            .map(|(node_map, _)| node_map)
+            .ok_or_else(|| {
+                format!(
+                    "Failed to generate a solvable map out of {} candidates",
+                    candidates
+                )
+            })
+    }
+
+    /// Scores a solvable map's already-computed solve `path` by its length,
+    /// the ratio of `Locked` to `Open` edges, and how many distinct layers
+    /// have a key to collect. Higher is more varied/difficult.
+    fn score(&self, path: &[Pos]) -> f64 {
+        let path_len = path.len() as f64;
+
+        let (locked, open) =
+            self.connections
+                .values()
+                .fold((0u32, 0u32), |(locked, open), state| match state {
+                    ConnectState::Locked => (locked + 1, open),
+                    ConnectState::Open => (locked, open + 1),
+                    ConnectState::Shortcut => (locked, open),
+                });
+        let lock_ratio = if open == 0 {
+            0.0
+        } else {
+            locked as f64 / open as f64
+        };
+
+        let key_layers = self
+            .nodes
+            .iter()
+            .flatten()
+            .filter_map(|node| node.key)
+            .collect::<HashSet<u8>>()
+            .len() as f64;
+
+        path_len + lock_ratio * 4.0 + key_layers * 2.0
+    }
 
+    fn generate_once<R: Rng>(
+        width: isize,
+        height: isize,
+        layers: u8,
+        rng: &mut R,
+    ) -> Result<NodeMap, String> {
         let mut initial_map = NodeMap::with_size(width as usize, height as usize);
 
         let initial_pos = Pos(
-            rng.gen_range(0, initial_map.height),
-            rng.gen_range(0, initial_map.width),
+            rng.gen_range(0..initial_map.height),
+            rng.gen_range(0..initial_map.width),
         );
 
         initial_map.set_node(initial_pos, Node::default());
@@ -235,7 +442,7 @@ impl NodeMap {
         let mut state = initial_state;
 
         while state.layer < layers && retries_left >= 0 {
-            match state.generate_next_state(&mut rng) {
+            match state.generate_next_state(rng) {
                 Ok(next_state) => state = next_state,
                 _ => {
                     retries_left -= 1;
@@ -249,7 +456,7 @@ impl NodeMap {
             return Err(format!("Failed to generate after {} retries", 10));
         }
 
-        state = state.generate_final_state(&mut rng).unwrap();
+        state = state.generate_final_state(rng).unwrap();
 
         Ok(state.node_map.to_owned())
     }
@@ -276,14 +483,257 @@ impl NodeMap {
             .unwrap() = Some(node);
     }
 
-    pub fn get_connection(&self, conn: Line) -> Option<&Connection> {
-        self.connections
-            .iter()
-            .find(|other_conn| conn == other_conn.line)
+    /// Returns an owned `Connection` rather than `&Connection`: this is a
+    /// real signature change from the `Vec<Connection>` days, not
+    /// a no-op, but it's harmless since `Connection` is `Copy`.
+    pub fn get_connection(&self, conn: Line) -> Option<Connection> {
+        self.connections.get(&conn).map(|&state| Connection {
+            line: conn,
+            state,
+        })
     }
 
     pub fn add_connection(&mut self, conn: Connection) {
-        self.connections.push(conn);
+        if self.connections.insert(conn.line, conn.state).is_none() {
+            self.connection_order.push(conn.line);
+        }
+    }
+
+    fn pos_of(&self, index: usize) -> Pos {
+        Pos(index as isize / self.width, index as isize % self.width)
+    }
+
+    fn connections_at(&self, pos: Pos) -> impl Iterator<Item = Connection> + '_ {
+        self.connection_order
+            .iter()
+            .filter(move |line| line.0 == pos || line.1 == pos)
+            .map(move |&line| Connection {
+                line,
+                state: self.connections[&line],
+            })
+    }
+
+    /// Searches the expanded `(Pos, key_mask)` state space for a path from the
+    /// layer-0 node to the highest-layer node, returning the path if one exists.
+    ///
+    /// A `Locked` edge is only passable once the bit for the higher-layer
+    /// endpoint is set in the mask, and arriving at a node sets the bit for its
+    /// key (if any) before that node's neighbours are explored. This ensures a
+    /// key is always collected strictly before its matching lock is needed.
+    pub fn solve(&self) -> Option<Vec<Pos>> {
+        let (start, _) = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, node)| node.as_ref().map(|node| (self.pos_of(i), node)))
+            .find(|(_, node)| node.layer == 0)?;
+
+        let (end, _) = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, node)| node.as_ref().map(|node| (self.pos_of(i), node)))
+            .max_by_key(|(_, node)| node.layer)?;
+
+        let start_state = (start, 0u32);
+
+        let mut frontier = VecDeque::new();
+        let mut parents: HashMap<(Pos, u32), (Pos, u32)> = HashMap::new();
+        let mut visited = HashSet::new();
+
+        frontier.push_back(start_state);
+        visited.insert(start_state);
+
+        while let Some(state @ (pos, mask)) = frontier.pop_front() {
+            if pos == end {
+                let mut path = vec![pos];
+                let mut current = state;
+
+                while let Some(&prev) = parents.get(&current) {
+                    path.push(prev.0);
+                    current = prev;
+                }
+
+                path.reverse();
+
+                return Some(path);
+            }
+
+            for conn in self.connections_at(pos) {
+                let other = conn.line.other(pos);
+
+                let passable = match conn.state {
+                    ConnectState::Open | ConnectState::Shortcut => true,
+                    ConnectState::Locked => {
+                        let higher_layer = self
+                            .get_node(pos)
+                            .zip(self.get_node(other))
+                            .map(|(a, b)| a.layer.max(b.layer))
+                            .unwrap_or(0);
+
+                        mask & (1 << higher_layer) != 0
+                    }
+                };
+
+                if !passable {
+                    continue;
+                }
+
+                let mut next_mask = mask;
+
+                if let Some(layer) = self.get_node(other).and_then(|node| node.key) {
+                    next_mask |= 1 << layer;
+                }
+
+                let next_state = (other, next_mask);
+
+                if visited.insert(next_state) {
+                    parents.insert(next_state, state);
+                    frontier.push_back(next_state);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Renders this map as a Graphviz DOT graph, with each node labeled by
+    /// its layer/key and edges styled by `ConnectState`.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph NodeMap {\n");
+
+        for (i, node) in self.nodes.iter().enumerate() {
+            if let Some(node) = node {
+                let pos = self.pos_of(i);
+
+                let label = match node.key {
+                    Some(key) => format!("L{}\\nkey {}", node.layer, key),
+                    None => format!("L{}", node.layer),
+                };
+
+                dot.push_str(&format!(
+                    "  \"{},{}\" [label=\"{}\"];\n",
+                    pos.0, pos.1, label
+                ));
+            }
+        }
+
+        for line in &self.connection_order {
+            let style = match self.connections[line] {
+                ConnectState::Open => "style=solid".to_string(),
+                ConnectState::Locked => format!("style=solid, label=\"{}\"", LOCK),
+                ConnectState::Shortcut => "style=dashed, color=grey".to_string(),
+            };
+
+            dot.push_str(&format!(
+                "  \"{},{}\" -- \"{},{}\" [{}];\n",
+                line.0 .0, line.0 .1, line.1 .0, line.1 .1, style
+            ));
+        }
+
+        dot.push_str("}\n");
+
+        dot
+    }
+
+    /// Rebuilds this map's connections as a spanning tree over every placed
+    /// node: candidate adjacencies are unioned in randomized order, so the
+    /// skeleton is guaranteed connected, and every adjacency that would
+    /// close a cycle becomes a `Shortcut` instead.
+    fn connect_spanning_tree<R: Rng>(&mut self, rng: &mut R) {
+        self.connections.clear();
+        self.connection_order.clear();
+
+        let positions: Vec<Pos> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, node)| node.as_ref().map(|_| self.pos_of(i)))
+            .collect();
+
+        let index_of: HashMap<Pos, usize> = positions
+            .iter()
+            .enumerate()
+            .map(|(index, &pos)| (pos, index))
+            .collect();
+
+        let mut candidates: Vec<Line> = positions
+            .iter()
+            .flat_map(|&pos| {
+                let index_of = &index_of;
+
+                DIRECTIONS.iter().copied().filter_map(move |offset| {
+                    let other = pos + offset;
+                    index_of.contains_key(&other).then_some(Line(pos, other))
+                })
+            })
+            .collect::<HashSet<Line>>()
+            .into_iter()
+            .collect();
+
+        // `HashSet`'s iteration order is randomized per-process, so sort
+        // before shuffling to keep this reproducible under a seeded rng.
+        candidates.sort_by_key(|line| ((line.0 .0, line.0 .1), (line.1 .0, line.1 .1)));
+        candidates.shuffle(rng);
+
+        let mut components = UnionFind::new(positions.len());
+
+        for line in candidates {
+            let a = index_of[&line.0];
+            let b = index_of[&line.1];
+
+            let layer_a = self.get_node(line.0).unwrap().layer;
+            let layer_b = self.get_node(line.1).unwrap().layer;
+
+            let state = if components.union(a, b) {
+                if layer_a == layer_b {
+                    ConnectState::Open
+                } else {
+                    ConnectState::Locked
+                }
+            } else {
+                ConnectState::Shortcut
+            };
+
+            self.add_connection(Connection { line, state });
+        }
+    }
+}
+
+/// A disjoint-set forest used to build the spanning-tree connection mode.
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(size: usize) -> UnionFind {
+        UnionFind {
+            parent: (0..size).collect(),
+        }
+    }
+
+    fn find(&mut self, index: usize) -> usize {
+        if self.parent[index] != index {
+            self.parent[index] = self.find(self.parent[index]);
+        }
+
+        self.parent[index]
+    }
+
+    /// Unions the components containing `a` and `b`, returning whether they
+    /// were previously disjoint (i.e. whether this edge joins the forest
+    /// rather than closing a cycle).
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let root_a = self.find(a);
+        let root_b = self.find(b);
+
+        if root_a == root_b {
+            return false;
+        }
+
+        self.parent[root_a] = root_b;
+
+        true
     }
 }
 
@@ -297,15 +747,12 @@ struct GeneratorState {
 }
 
 impl GeneratorState {
-    fn generate_next_state(
-        &self,
-        rng: &mut rand::rngs::ThreadRng,
-    ) -> Result<GeneratorState, &'static str> {
+    fn generate_next_state<R: Rng>(&self, rng: &mut R) -> Result<GeneratorState, &'static str> {
         let mut next_state = self.clone();
         next_state.prev_state = Some(Box::new(self.clone()));
 
         next_state.layer += 1;
-        next_state.amount = rng.gen_range(4, 8);
+        next_state.amount = rng.gen_range(4..8);
 
         for _ in 0..next_state.amount {
             next_state.generate_node(rng)?;
@@ -352,10 +799,7 @@ impl GeneratorState {
         Ok(next_state)
     }
 
-    fn generate_final_state(
-        &self,
-        rng: &mut rand::rngs::ThreadRng,
-    ) -> Result<GeneratorState, &'static str> {
+    fn generate_final_state<R: Rng>(&self, rng: &mut R) -> Result<GeneratorState, &'static str> {
         let mut final_state = self.clone();
         final_state.prev_state = Some(Box::new(self.clone()));
         final_state.layer += 1;
@@ -404,14 +848,14 @@ impl GeneratorState {
         Ok(final_state)
     }
 
-    fn generate_node(&mut self, rng: &mut rand::rngs::ThreadRng) -> Result<(), &'static str> {
+    fn generate_node<R: Rng>(&mut self, rng: &mut R) -> Result<(), &'static str> {
         let spaces = self.available_spaces();
 
         if spaces.len() == 0 {
             return Err("No available spaces!");
         }
 
-        let random_index = rng.gen_range(0, spaces.len());
+        let random_index = rng.gen_range(0..spaces.len());
         let random_space = spaces.get(random_index).unwrap();
 
         self.pos_list.push(*random_space);
@@ -429,7 +873,8 @@ impl GeneratorState {
     }
 
     fn available_spaces_with_skip(&self, skip: usize) -> Vec<Pos> {
-        self.pos_list
+        let mut spaces = self
+            .pos_list
             .iter()
             .skip(skip)
             .flat_map(move |pos: &Pos| {
@@ -449,7 +894,13 @@ impl GeneratorState {
             .filter(|pos| !self.pos_list.contains(pos))
             .collect::<HashSet<Pos>>()
             .into_iter()
-            .collect::<Vec<Pos>>()
+            .collect::<Vec<Pos>>();
+
+        // `HashSet`'s iteration order is randomized per-process, so sort here
+        // to make generation reproducible under a seeded rng.
+        spaces.sort_by_key(|pos| (pos.0, pos.1));
+
+        spaces
     }
 
     fn generate_connections(&mut self) {
@@ -498,8 +949,6 @@ impl GeneratorState {
                     line,
                     state: ConnectState::Open,
                 })
-                .collect::<HashSet<Connection>>()
-                .into_iter()
                 .collect::<Vec<Connection>>()
             {
                 self.node_map.add_connection(conn);
@@ -518,8 +967,6 @@ impl GeneratorState {
                     line,
                     state: ConnectState::Shortcut,
                 })
-                .collect::<HashSet<Connection>>()
-                .into_iter()
                 .collect::<Vec<Connection>>()
             {
                 self.node_map.add_connection(conn);
@@ -527,3 +974,155 @@ impl GeneratorState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked_corridor(key: Option<u8>) -> NodeMap {
+        let mut node_map = NodeMap::with_size(3, 1);
+
+        node_map.set_node(Pos(0, 0), Node::with_layer(0));
+        node_map.set_node(Pos(0, 1), Node { layer: 0, key });
+        node_map.set_node(Pos(0, 2), Node::with_layer(1));
+
+        node_map.add_connection(Connection {
+            line: Line(Pos(0, 0), Pos(0, 1)),
+            state: ConnectState::Open,
+        });
+        node_map.add_connection(Connection {
+            line: Line(Pos(0, 1), Pos(0, 2)),
+            state: ConnectState::Locked,
+        });
+
+        node_map
+    }
+
+    #[test]
+    fn solve_finds_a_path_once_the_key_is_collected() {
+        let node_map = locked_corridor(Some(1));
+
+        let path = node_map.solve().expect("should be solvable");
+
+        assert_eq!(path, vec![Pos(0, 0), Pos(0, 1), Pos(0, 2)]);
+    }
+
+    #[test]
+    fn solve_fails_without_the_key() {
+        let node_map = locked_corridor(None);
+
+        assert!(node_map.solve().is_none());
+    }
+
+    #[test]
+    fn to_dot_renders_nodes_and_styled_edges() {
+        let node_map = locked_corridor(Some(1));
+
+        let dot = node_map.to_dot();
+
+        assert!(dot.starts_with("graph NodeMap {\n"));
+        assert!(dot.contains("\"0,0\" [label=\"L0\"];\n"));
+        assert!(dot.contains("\"0,1\" [label=\"L0\\nkey 1\"];\n"));
+        assert!(dot.contains("\"0,2\" [label=\"L1\"];\n"));
+        assert!(dot.contains("\"0,0\" -- \"0,1\" [style=solid];\n"));
+        assert!(dot.contains(&format!(
+            "\"0,1\" -- \"0,2\" [style=solid, label=\"{}\"];\n",
+            LOCK
+        )));
+        assert!(dot.ends_with("}\n"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn connections_round_trip_through_json() {
+        let node_map = locked_corridor(Some(1));
+
+        let json = serde_json::to_string(&node_map).unwrap();
+        let restored: NodeMap = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(format!("{:?}", node_map), format!("{:?}", restored));
+    }
+
+    #[test]
+    fn generate_seeded_accepts_max_layers_without_panicking() {
+        // `generate_once` produces a final node one layer beyond `layers`, so
+        // `layers == MAX_LAYERS` must stay within the solver's `u32` key mask.
+        // Needs a grid big enough to fit `MAX_LAYERS` worth of nodes.
+        NodeMap::generate_seeded(40, 40, MAX_LAYERS, 7).unwrap();
+    }
+
+    #[test]
+    fn generate_seeded_rejects_layers_above_max() {
+        assert!(NodeMap::generate_seeded(40, 40, MAX_LAYERS + 1, 7).is_err());
+    }
+
+    #[test]
+    fn generate_seeded_is_deterministic() {
+        let a = NodeMap::generate_seeded(10, 10, 4, 42).unwrap();
+        let b = NodeMap::generate_seeded(10, 10, 4, 42).unwrap();
+
+        assert_eq!(format!("{:?}", a), format!("{:?}", b));
+    }
+
+    #[test]
+    fn score_rewards_locks_and_keys_over_a_plain_path() {
+        let mut plain = NodeMap::with_size(2, 1);
+        plain.set_node(Pos(0, 0), Node::with_layer(0));
+        plain.set_node(Pos(0, 1), Node::with_layer(1));
+        plain.add_connection(Connection {
+            line: Line(Pos(0, 0), Pos(0, 1)),
+            state: ConnectState::Open,
+        });
+        let plain_path = plain.solve().unwrap();
+
+        let locked = locked_corridor(Some(1));
+        let locked_path = locked.solve().unwrap();
+
+        assert!(locked.score(&locked_path) > plain.score(&plain_path));
+    }
+
+    #[test]
+    fn generate_best_errors_when_no_candidate_can_be_generated() {
+        // A 4x4 grid can't fit the nodes 6 layers calls for, so every
+        // candidate fails and `generate_best` should report that rather
+        // than panicking on an empty candidate set.
+        let result = NodeMap::generate_best(4, 4, 6, 3);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn spanning_tree_is_connected_and_classifies_edges_by_layer() {
+        let mut node_map = NodeMap::with_size(3, 3);
+
+        for row in 0..3 {
+            for col in 0..3 {
+                node_map.set_node(Pos(row, col), Node::with_layer((row + col) as u8));
+            }
+        }
+
+        node_map.connect_spanning_tree(&mut StdRng::seed_from_u64(1));
+
+        let tree_edges: Vec<&Line> = node_map
+            .connection_order
+            .iter()
+            .filter(|line| node_map.connections[line] != ConnectState::Shortcut)
+            .collect();
+
+        // A forest with as many edges as `nodes - 1` spanning every node must
+        // be a single connected, acyclic tree.
+        assert_eq!(tree_edges.len(), 9 - 1);
+
+        for line in tree_edges {
+            let layer_a = node_map.get_node(line.0).unwrap().layer;
+            let layer_b = node_map.get_node(line.1).unwrap().layer;
+            let state = node_map.connections[line];
+
+            if layer_a == layer_b {
+                assert_eq!(state, ConnectState::Open);
+            } else {
+                assert_eq!(state, ConnectState::Locked);
+            }
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use derive_more::{Add, AddAssign, Into, Mul, MulAssign, Not};
+use std::hash::{Hash, Hasher};
 
 #[derive(
     Default, Debug, Copy, Clone, PartialEq, Eq, Add, AddAssign, Mul, MulAssign, Not, Into, Hash,
 )]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pos(pub isize, pub isize);
 
 impl Pos {
@@ -15,7 +17,8 @@ impl Pos {
     }
 }
 
-#[derive(Copy, Clone, Debug, Eq, Hash)]
+#[derive(Copy, Clone, Debug, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Line(pub Pos, pub Pos);
 
 impl PartialEq for Line {
@@ -24,7 +27,33 @@ impl PartialEq for Line {
     }
 }
 
+impl Hash for Line {
+    // Must agree with the order-insensitive `PartialEq` impl above, so hash
+    // the endpoints in a canonical (row, col) order rather than field order.
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        if (self.0 .0, self.0 .1) <= (self.1 .0, self.1 .1) {
+            self.0.hash(state);
+            self.1.hash(state);
+        } else {
+            self.1.hash(state);
+            self.0.hash(state);
+        }
+    }
+}
+
+impl Line {
+    /// Returns the endpoint of this line that isn't `pos`.
+    pub fn other(&self, pos: Pos) -> Pos {
+        if self.0 == pos {
+            self.1
+        } else {
+            self.0
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ConnectState {
     Open,
     Locked,
@@ -32,6 +61,7 @@ pub enum ConnectState {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Connection {
     pub line: Line,
     pub state: ConnectState,